@@ -1,16 +1,43 @@
+use std::io::{BufRead, BufReader, IsTerminal};
+use std::path::Path;
 use std::{env, fs, io};
 
+/// The shape of the output `run` produces, selected with an output flag.
+///
+/// # Variants
+///
+/// * `Plain` - the default, human-readable `path:lineno: line` style, optionally
+///   color-highlighting the matched substring when stdout is a TTY
+/// * `Json` - one JSON object per match, e.g. for piping into `jq`
+/// * `Count` - only the number of matching lines per file
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OutputFormat {
+    Plain,
+    Json,
+    Count,
+}
+
 /// Simple struct to hold the configuration for the mini grep cli tool
 ///
 /// # Properties
 ///
 /// * `query` - The string to search for
-/// * `filename` - The file to search in (as string filepath)
+/// * `filenames` - The files (or directories, with `recursive`) to search in
+/// * `ignore_case` - Whether to ignore case when searching (`-i`)
+/// * `show_line_numbers` - Whether to print line numbers alongside matches (`-n`)
+/// * `format` - The [OutputFormat] to render matches with (`-c` selects `Count`,
+///   `--json` selects `Json`)
+/// * `invert_match` - Whether to print lines that do *not* match the query (`-v`)
+/// * `recursive` - Whether to recurse into directories found among `filenames` (`-r`)
 ///
 pub struct Config {
     pub query: String,
-    pub filename: String,
+    pub filenames: Vec<String>,
     pub ignore_case: bool,
+    pub show_line_numbers: bool,
+    pub format: OutputFormat,
+    pub invert_match: bool,
+    pub recursive: bool,
 }
 
 impl Config {
@@ -31,31 +58,49 @@ impl Config {
     ///
     /// let config = Config::new("needle", "bar.txt");
     /// assert_eq!(config.query, "needle");
-    /// assert_eq!(config.filename, "bar.txt");
+    /// assert_eq!(config.filenames, vec!["bar.txt".to_string()]);
     ///
     /// ```
     pub fn new(query: &str, filename: &str) -> Config {
         Config {
             query: query.to_string(),
-            filename: filename.to_string(),
+            filenames: vec![filename.to_string()],
             ignore_case: env::var("MINIGREP_IGNORE_CASE").is_ok()
                 && env::var("MINIGREP_IGNORE_CASE").unwrap() == "1",
+            show_line_numbers: false,
+            format: OutputFormat::Plain,
+            invert_match: false,
+            recursive: false,
         }
     }
 
-    /// Create a new Config instance from a vector of arguments
-    /// The first argument is ignored, as it is the program name
-    /// The second argument is the **query** string
-    /// The third argument is the **filename**
+    /// Create a new Config instance from an iterator of arguments, grep-style.
+    /// The first item is ignored, as it is the program name.
+    ///
+    /// Remaining items are either flags or positional arguments: the **query**
+    /// followed by one or more **filenames** (or directories, with `-r`), which may
+    /// appear in any order relative to the flags. Short flags may be bundled behind
+    /// a single `-`, grep-style (`-in` is equivalent to `-i -n`):
+    ///
+    /// * `-i` - force case-insensitive matching (overrides `MINIGREP_IGNORE_CASE`)
+    /// * `-n` - print line numbers alongside matches
+    /// * `-c` - print only the number of matching lines ([OutputFormat::Count])
+    /// * `--json` - print one JSON object per match ([OutputFormat::Json])
+    /// * `-v` - invert the match, printing lines that do *not* contain the query
+    /// * `-r` - recurse into any directories found among the filenames
+    ///
+    /// A bare `-` is treated as a filename, not a flag, meaning "read from stdin"
+    /// (see [read_file]).
     ///
     /// # Arguments
     ///
-    /// * `args` - A vector of arguments
+    /// * `args` - An iterator of arguments, e.g. `env::args()`
+    /// * `dbg` - Whether to print the parsed query/filenames to stderr before returning
     ///
     /// # Returns
     ///
-    /// A new [Config] instance
-    ///
+    /// A new [Config] instance, or a descriptive error if a flag is unknown or the
+    /// query/filename positional arguments are missing
     ///
     /// # Examples
     ///
@@ -63,28 +108,74 @@ impl Config {
     ///
     /// use minigrep::Config;
     ///
-    /// let args = vec![String::from("program_name"), String::from("needle"), String::from("bar.txt")];
-    /// let config = Config::new_from_args(&args,false).unwrap();
+    /// let args = vec![String::from("program_name"), String::from("-i"), String::from("needle"), String::from("bar.txt")];
+    /// let config = Config::new_from_args(args.into_iter(), false).unwrap();
     /// assert_eq!(config.query, "needle");
-    /// assert_eq!(config.filename, "bar.txt");
+    /// assert_eq!(config.filenames, vec!["bar.txt".to_string()]);
+    /// assert!(config.ignore_case);
     ///
     /// ```
     ///
-    /// # Panics
-    ///
-    /// If the number of arguments is not 3
-    ///
-    pub fn new_from_args(args: &[String], dbg: bool) -> Result<Config, &'static str> {
-        if args.len() < 3 {
-            return Err("Not enough arguments. USAGE is: minigrep <query> <filename>");
+    pub fn new_from_args(
+        mut args: impl Iterator<Item = String>,
+        dbg: bool,
+    ) -> Result<Config, String> {
+        args.next(); // the program name, unused
+
+        let mut ignore_case = env::var("MINIGREP_IGNORE_CASE").is_ok()
+            && env::var("MINIGREP_IGNORE_CASE").unwrap() == "1";
+        let mut show_line_numbers = false;
+        let mut format = OutputFormat::Plain;
+        let mut invert_match = false;
+        let mut recursive = false;
+        let mut positional = Vec::new();
+
+        for arg in args {
+            if arg == "-" {
+                // A bare "-" is the stdin marker, not a flag.
+                positional.push(arg);
+            } else if let Some(long_flag) = arg.strip_prefix("--") {
+                match long_flag {
+                    "json" => format = OutputFormat::Json,
+                    _ => return Err(format!("Unknown flag: {}", arg)),
+                }
+            } else if let Some(short_flags) = arg.strip_prefix('-') {
+                for flag in short_flags.chars() {
+                    match flag {
+                        'i' => ignore_case = true,
+                        'n' => show_line_numbers = true,
+                        'c' => format = OutputFormat::Count,
+                        'v' => invert_match = true,
+                        'r' => recursive = true,
+                        _ => return Err(format!("Unknown flag: -{}", flag)),
+                    }
+                }
+            } else {
+                positional.push(arg);
+            }
+        }
+
+        if positional.len() < 2 {
+            return Err(
+                "Not enough arguments. USAGE is: minigrep [-incrv] [--json] <query> <filename>..."
+                    .to_string(),
+            );
         }
 
         if dbg {
-            println!("Searching for {}", &args[1].clone());
-            println!("In file {}", &args[2].clone());
+            eprintln!("Searching for {}", &positional[0]);
+            eprintln!("In files {:?}", &positional[1..]);
         }
 
-        Ok(Config::new(&args[1].clone(), &args[2].clone()))
+        Ok(Config {
+            query: positional[0].clone(),
+            filenames: positional[1..].to_vec(),
+            ignore_case,
+            show_line_numbers,
+            format,
+            invert_match,
+            recursive,
+        })
     }
 }
 
@@ -95,6 +186,8 @@ impl Config {
 ///
 /// * `line_number` - The line number
 /// * `line` - The line itself
+/// * `source` - The filename the line came from, set by [run] whenever more than
+///   one file is being searched, so callers can format output consistently
 ///
 /// # Examples
 ///
@@ -108,6 +201,7 @@ impl Config {
 pub struct SearchResult {
     pub line: String,
     pub line_number: usize,
+    pub source: Option<String>,
 }
 
 impl SearchResult {
@@ -118,7 +212,11 @@ impl SearchResult {
     /// * `line_number` - The line number of the line that was found
     ///
     pub fn new(line: String, line_number: usize) -> SearchResult {
-        SearchResult { line, line_number }
+        SearchResult {
+            line,
+            line_number,
+            source: None,
+        }
     }
 }
 
@@ -126,38 +224,108 @@ impl SearchResult {
 ///
 /// # Arguments
 ///
-/// * `args` - A vector of command line arguments
+/// * `args` - An iterator of command line arguments, e.g. `env::args()`
 ///
 /// # Returns
 ///
 /// A [Config] struct
 ///
 ///
-pub fn parse_config(args: &[String]) -> Config {
-    let config = Config::new_from_args(&args, true);
+pub fn parse_config(args: impl Iterator<Item = String>) -> Config {
+    let config = Config::new_from_args(args, true);
     match config {
         Ok(c) => c,
         Err(e) => panic!("Error while parsing the cli arguments: {}", e),
     }
 }
 
-/// `read_file` reads the contents of a file into a string
+/// `read_file` opens `path` and hands back a lazy iterator over its lines, rather
+/// than reading the whole thing into memory up front.
+///
+/// Passing `-` as the path reads from stdin instead, so the same pipeline can grep
+/// a piped stream.
 ///
 /// # Arguments
 ///
-/// * `filename` - The file to read
+/// * `path` - The file to read, or `-` for stdin
 ///
 /// # Returns
 ///
-/// A string containing the contents of the file or an error
+/// An iterator yielding each line of the file (or stdin) as an `io::Result<String>`,
+/// or an error if the file could not be opened.
 ///
-pub fn read_file(config: &Config) -> Result<String, io::Error> {
-    let contents = fs::read_to_string(&config.filename)?;
-    Ok(contents)
+pub fn read_file(path: &str) -> io::Result<impl Iterator<Item = io::Result<String>>> {
+    let reader: Box<dyn BufRead> = if path == "-" {
+        Box::new(BufReader::new(io::stdin()))
+    } else {
+        Box::new(BufReader::new(fs::File::open(path)?))
+    };
+
+    Ok(reader.lines())
+}
+
+/// Resolve `paths` into a flat list of files to search, expanding directories into
+/// their contents when `recursive` is set. Any other path (including `-` and paths
+/// that do not exist) is passed through unchanged. A path that is a directory while
+/// `recursive` is `false` is skipped with a warning on stderr rather than failing
+/// the whole run.
+fn collect_paths(paths: &[String], recursive: bool) -> Vec<String> {
+    let mut collected = Vec::new();
+
+    for path in paths {
+        match fs::metadata(path) {
+            Ok(meta) if meta.is_dir() => {
+                if recursive {
+                    collect_dir(Path::new(path), &mut collected);
+                } else {
+                    eprintln!(
+                        "Warning: {} is a directory, skipping (use -r to search recursively)",
+                        path
+                    );
+                }
+            }
+            _ => collected.push(path.clone()),
+        }
+    }
+
+    collected
+}
+
+/// Recursively walk `dir`, appending every regular file found to `collected`.
+fn collect_dir(dir: &Path, collected: &mut Vec<String>) {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Warning: could not read directory {}: {}", dir.display(), e);
+            return;
+        }
+    };
+
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            collect_dir(&path, collected);
+        } else if let Some(path) = path.to_str() {
+            collected.push(path.to_string());
+        }
+    }
 }
 
 /// `run` is the main entry point for the mini grep cli tool
-/// given a [Config] created before, it will read the file and search for the query string
+/// given a [Config] created before, it will read every configured file (expanding
+/// directories first when `recursive` is set) and search each one in turn for the
+/// query string, printing each match as it is found instead of collecting them all
+/// beforehand.
+///
+/// It branches on the flags stored on `config`: `format` selects between the
+/// human-readable, JSON and count-only renderings, `show_line_numbers` prefixes
+/// each match with its line number, and `invert_match` is threaded down into the
+/// search functions themselves so that non-matching lines are reported instead.
+/// When more than one file is involved, every [SearchResult] is tagged with its
+/// originating filename so matches stay grouped per file, grep's `path:lineno: line`
+/// style. Files that fail to open emit a warning to stderr and are skipped rather
+/// than aborting the whole run. In `Plain` format, the matched substring is
+/// highlighted in color when stdout is a TTY, and printed as-is when piped.
 ///
 /// # Arguments
 ///
@@ -165,116 +333,266 @@ pub fn read_file(config: &Config) -> Result<String, io::Error> {
 ///
 /// # Returns
 ///
-/// A [Result] containing a vector of [SearchResult] or an error.
-/// The search function is agnostic about the type of Error, it uses [Box] with a dynamic
-/// dispatch to handle the error
+/// A [Result] that is `Ok` once every file has been searched. The search itself is
+/// agnostic about the type of Error, it uses [Box] with a dynamic dispatch to
+/// handle the error
 pub fn run(config: Config) -> Result<(), Box<dyn std::error::Error>> {
-    // Read the file content
-    let contents = read_file(&config)?;
-    //println!("With text:\n{}", contents);
+    let paths = collect_paths(&config.filenames, config.recursive);
+    let multiple_files = paths.len() > 1;
+    let query = config.query.as_str();
+    let colorize = config.format == OutputFormat::Plain && io::stdout().is_terminal();
+
+    let mut found_any = false;
+    for path in &paths {
+        let lines = match read_file(path) {
+            Ok(lines) => lines,
+            Err(e) => {
+                eprintln!("Warning: could not open {}: {}", path, e);
+                continue;
+            }
+        };
+        let numbered_lines = lines.filter_map(Result::ok).enumerate();
+
+        let results: Box<dyn Iterator<Item = SearchResult>> = if config.ignore_case {
+            Box::new(search_case_insensitive(
+                query,
+                numbered_lines,
+                config.invert_match,
+            ))
+        } else {
+            Box::new(search(query, numbered_lines, config.invert_match))
+        };
+
+        if config.format == OutputFormat::Count {
+            let count = results.count();
+            if multiple_files {
+                println!("{}: {}", path, count);
+            } else {
+                println!("{}", count);
+            }
+            continue;
+        }
 
-    let mut counter = 1;
-    let results = if config.ignore_case {
-        search_case_insensitive(&config.query, &contents)
+        for mut result in results {
+            found_any = true;
+            if multiple_files {
+                result.source = Some(path.clone());
+            }
+            match config.format {
+                OutputFormat::Json => println!("{}", format_json(&result)),
+                OutputFormat::Plain => println!("{}", format_plain(&config, &result, colorize)),
+                OutputFormat::Count => unreachable!("handled above"),
+            }
+        }
+    }
+
+    if !found_any && config.format == OutputFormat::Plain {
+        println!("---> No results found");
+    }
+
+    Ok(())
+}
+
+/// Render a single [SearchResult] in the default human-readable format, prefixing
+/// it with its filename when `result.source` is set and with its line number when
+/// `show_line_numbers` is set, and highlighting the matched substring when
+/// `colorize` is `true`.
+fn format_plain(config: &Config, result: &SearchResult, colorize: bool) -> String {
+    let line = if colorize {
+        highlight(&result.line, &config.query, config.ignore_case)
     } else {
-        search(&config.query, &contents)
+        result.line.clone()
     };
 
-    for result in results {
-        println!(
-            "Finding #{} at line {} :: {}",
-            counter, &result.line_number, &result.line
-        );
-        counter += 1;
+    match (&result.source, config.show_line_numbers) {
+        (Some(source), true) => format!("{}:{}: {}", source, result.line_number, line),
+        (Some(source), false) => format!("{}: {}", source, line),
+        (None, true) => format!("{}: {}", result.line_number, line),
+        (None, false) => line,
     }
+}
 
-    Ok(())
+const HIGHLIGHT_START: &str = "\x1b[1;31m";
+const HIGHLIGHT_END: &str = "\x1b[0m";
+
+/// Wrap every occurrence of `query` in `line` with ANSI bold-red escape codes.
+fn highlight(line: &str, query: &str, ignore_case: bool) -> String {
+    if query.is_empty() {
+        return line.to_string();
+    }
+
+    let mut highlighted = String::with_capacity(line.len());
+    let mut cursor = 0;
+
+    while let Some((offset, len)) = find_match(&line[cursor..], query, ignore_case) {
+        let start = cursor + offset;
+        let end = start + len;
+        highlighted.push_str(&line[cursor..start]);
+        highlighted.push_str(HIGHLIGHT_START);
+        highlighted.push_str(&line[start..end]);
+        highlighted.push_str(HIGHLIGHT_END);
+        cursor = end;
+    }
+    highlighted.push_str(&line[cursor..]);
+
+    highlighted
+}
+
+/// Find the byte offset and length of the first occurrence of `query` in `haystack`.
+///
+/// For `ignore_case`, every char-boundary-aligned window of `haystack` the same
+/// length (in chars) as `query` is lowercased and compared, so the returned offsets
+/// always index into the original (not lowercased) `haystack` — lowercasing a
+/// `char` can change its UTF-8 byte length (e.g. `İ`), so comparing against a
+/// fully-lowercased haystack would misalign the offsets.
+fn find_match(haystack: &str, query: &str, ignore_case: bool) -> Option<(usize, usize)> {
+    if !ignore_case {
+        return haystack.find(query).map(|start| (start, query.len()));
+    }
+
+    let query_lower = query.to_lowercase();
+    let query_char_count = query.chars().count();
+    let char_starts: Vec<usize> = haystack.char_indices().map(|(i, _)| i).collect();
+
+    for start_idx in 0..char_starts.len() {
+        let start = char_starts[start_idx];
+        let end_idx = start_idx + query_char_count;
+        let end = match char_starts.get(end_idx) {
+            Some(&byte) => byte,
+            None if end_idx == char_starts.len() => haystack.len(),
+            None => break,
+        };
+        if haystack[start..end].to_lowercase() == query_lower {
+            return Some((start, end - start));
+        }
+    }
+
+    None
+}
+
+/// Render a single [SearchResult] as a one-line JSON object, e.g. for piping into
+/// `jq`: `{"file": "...", "line_number": 3, "line": "..."}`. `file` is `null` when
+/// only a single file was searched.
+fn format_json(result: &SearchResult) -> String {
+    let file = match &result.source {
+        Some(source) => format!("\"{}\"", json_escape(source)),
+        None => "null".to_string(),
+    };
+
+    format!(
+        "{{\"file\": {}, \"line_number\": {}, \"line\": \"{}\"}}",
+        file,
+        result.line_number,
+        json_escape(&result.line)
+    )
+}
+
+/// Escape a string for embedding in a JSON string literal.
+fn json_escape(s: &str) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
 }
 
-/// `search` searches for a query string in a string
-/// It returns a vector of [SearchResult]
+/// `search` searches for a query string among already-numbered lines
 ///
 /// # Arguments
 ///
 /// * `query` - The string to search for
-/// * `contents` - The string to search in
+/// * `lines` - An iterator of zero-based `(index, line)` pairs, as produced by
+///   `.enumerate()` over the output of [read_file]; each result's `line_number` is
+///   `index + 1`
+/// * `invert` - When `true`, yield lines that do *not* contain the query instead
 ///
 /// # Returns
 ///
-/// A vector of [SearchResult]
-pub fn search(query: &str, contents: &str) -> Vec<SearchResult> {
-    let mut results: Vec<SearchResult> = Vec::new();
-    for (i, line) in contents.lines().enumerate() {
-        if line.contains(query) {
-            let result = SearchResult::new(line.to_string(), i + 1);
-            results.push(result);
-        }
-    }
-
-    if results.len() == 0 {
-        println!("---> No results found");
-    }
-
-    results
+/// A lazy iterator of [SearchResult], built with `.filter().map()` instead of a
+/// pre-filled `Vec`
+pub fn search<'a>(
+    query: &'a str,
+    lines: impl Iterator<Item = (usize, String)> + 'a,
+    invert: bool,
+) -> impl Iterator<Item = SearchResult> + 'a {
+    lines
+        .filter(move |(_, line)| line.contains(query) != invert)
+        .map(|(i, line)| SearchResult::new(line, i + 1))
 }
 
-/// `search_case_insensitive` searches for a query string in a string. It's very similar
-/// to [search] but it ignores the case of the query and the contents
+/// `search_case_insensitive` searches for a query string among already-numbered lines.
+/// It's very similar to [search] but it ignores the case of the query and the contents.
+/// The query is lowercased a single time, outside the per-line check.
 ///
 /// # Arguments
 ///
 /// * `query` - The string to search for
-/// * `contents` - The string to search in
+/// * `lines` - An iterator of zero-based `(index, line)` pairs, as produced by
+///   `.enumerate()` over the output of [read_file]; each result's `line_number` is
+///   `index + 1`
+/// * `invert` - When `true`, yield lines that do *not* contain the query instead
 ///
 /// # Returns
 ///
-/// A vector of [SearchResult]
-pub fn search_case_insensitive(query: &str, contents: &str) -> Vec<SearchResult> {
-    let mut results: Vec<SearchResult> = Vec::new();
-    for (i, line) in contents.lines().enumerate() {
-        if line.to_lowercase().contains(&query.to_lowercase()) {
-            let result = SearchResult::new(line.to_string(), i + 1);
-            results.push(result);
-        }
-    }
-
-    if results.len() == 0 {
-        println!("---> No results found");
-    }
-
-    results
+/// A lazy iterator of [SearchResult]
+pub fn search_case_insensitive<'a>(
+    query: &'a str,
+    lines: impl Iterator<Item = (usize, String)> + 'a,
+    invert: bool,
+) -> impl Iterator<Item = SearchResult> + 'a {
+    let query = query.to_lowercase();
+    lines
+        .filter(move |(_, line)| line.to_lowercase().contains(&query) != invert)
+        .map(|(i, line)| SearchResult::new(line, i + 1))
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    fn numbered(content: &str) -> impl Iterator<Item = (usize, String)> + '_ {
+        content.lines().map(String::from).enumerate()
+    }
+
     #[test]
     fn parse_config_test() {
         let test_args = vec!["".to_string(), "query".to_string(), "filename".to_string()];
-        let config = parse_config(&test_args);
+        let config = parse_config(test_args.into_iter());
         assert_eq!(config.query, "query");
-        assert_eq!(config.filename, "filename");
+        assert_eq!(config.filenames, vec!["filename".to_string()]);
     }
 
     #[test]
     #[should_panic]
     fn parse_config_with_error() {
         let test_args = vec!["query".to_string(), "filename".to_string()];
-        parse_config(&test_args);
+        parse_config(test_args.into_iter());
     }
 
     #[test]
     fn read_file_test() {
-        let config = super::Config::new("needle", "./data/poem");
-        let result = read_file(&config);
+        let mut path = env::temp_dir();
+        path.push("minigrep_read_file_test.txt");
+        fs::write(&path, "needle in the haystack\n").unwrap();
+
+        let result = read_file(path.to_str().unwrap());
         assert!(result.is_ok());
+
+        fs::remove_file(&path).unwrap();
     }
 
     #[test]
     fn parse_config_with_too_few_args() {
         let test_args = vec!["".to_string(), "query".to_string()];
-        let config = Config::new_from_args(&test_args, false);
+        let config = Config::new_from_args(test_args.into_iter(), false);
         assert!(config.is_err());
     }
 
@@ -282,12 +600,124 @@ mod tests {
     #[should_panic]
     fn parse_config_panic_test() {
         let test_args = vec!["".to_string(), "query".to_string()];
-        let config = Config::new_from_args(&test_args, false);
+        let config = Config::new_from_args(test_args.into_iter(), false);
         if config.is_err() {
             panic!("Not enough arguments");
         }
     }
 
+    #[test]
+    fn parse_config_with_flags() {
+        let test_args = vec![
+            "".to_string(),
+            "-i".to_string(),
+            "-n".to_string(),
+            "needle".to_string(),
+            "filename".to_string(),
+        ];
+        let config = Config::new_from_args(test_args.into_iter(), false).unwrap();
+        assert_eq!(config.query, "needle");
+        assert_eq!(config.filenames, vec!["filename".to_string()]);
+        assert!(config.ignore_case);
+        assert!(config.show_line_numbers);
+    }
+
+    #[test]
+    fn parse_config_with_bundled_short_flags() {
+        let test_args = vec![
+            "".to_string(),
+            "-in".to_string(),
+            "needle".to_string(),
+            "filename".to_string(),
+        ];
+        let config = Config::new_from_args(test_args.into_iter(), false).unwrap();
+        assert!(config.ignore_case);
+        assert!(config.show_line_numbers);
+    }
+
+    #[test]
+    fn parse_config_with_unknown_flag() {
+        let test_args = vec![
+            "".to_string(),
+            "--bogus".to_string(),
+            "needle".to_string(),
+            "filename".to_string(),
+        ];
+        let config = Config::new_from_args(test_args.into_iter(), false);
+        assert!(config.is_err());
+    }
+
+    #[test]
+    fn parse_config_with_count_flag() {
+        let test_args = vec![
+            "".to_string(),
+            "-c".to_string(),
+            "needle".to_string(),
+            "filename".to_string(),
+        ];
+        let config = Config::new_from_args(test_args.into_iter(), false).unwrap();
+        assert_eq!(config.format, OutputFormat::Count);
+    }
+
+    #[test]
+    fn parse_config_with_json_flag() {
+        let test_args = vec![
+            "".to_string(),
+            "--json".to_string(),
+            "needle".to_string(),
+            "filename".to_string(),
+        ];
+        let config = Config::new_from_args(test_args.into_iter(), false).unwrap();
+        assert_eq!(config.format, OutputFormat::Json);
+    }
+
+    #[test]
+    fn highlight_wraps_every_occurrence() {
+        let result = highlight("needle in a needle stack", "needle", false);
+        assert_eq!(
+            result,
+            format!(
+                "{start}needle{end} in a {start}needle{end} stack",
+                start = HIGHLIGHT_START,
+                end = HIGHLIGHT_END
+            )
+        );
+    }
+
+    #[test]
+    fn highlight_ignore_case_matches_different_casing() {
+        let result = highlight("NEEDLE in a haystack", "needle", true);
+        assert_eq!(
+            result,
+            format!(
+                "{start}NEEDLE{end} in a haystack",
+                start = HIGHLIGHT_START,
+                end = HIGHLIGHT_END
+            )
+        );
+    }
+
+    #[test]
+    fn highlight_ignore_case_does_not_panic_on_byte_length_changing_lowercase() {
+        // 'İ' (U+0130) lowercases to a two-codepoint sequence "i̇", so offsets
+        // computed against a fully-lowercased haystack would no longer line up
+        // with the original string. This must not panic or misalign bytes even
+        // though no length-preserving case-fold match exists here.
+        let result = highlight("İstanbul", "istanbul", true);
+        assert_eq!(result, "İstanbul");
+    }
+
+    #[test]
+    fn format_json_includes_file_and_line() {
+        let mut result = SearchResult::new("needle in the haystack".to_string(), 3);
+        result.source = Some("bar.txt".to_string());
+        let json = format_json(&result);
+        assert_eq!(
+            json,
+            r#"{"file": "bar.txt", "line_number": 3, "line": "needle in the haystack"}"#
+        );
+    }
+
     #[test]
     fn run_test() {
         let query = "needle";
@@ -296,7 +726,7 @@ Rust:
 safe, fast, productive.
 needle in the haystack
 Pick three.";
-        let result = search(query, content);
+        let result: Vec<SearchResult> = search(query, numbered(content), false).collect();
         assert_eq!(result.len(), 1);
         assert_eq!(result[0].line, "needle in the haystack");
         assert_eq!(result[0].line_number, 3);
@@ -309,7 +739,7 @@ Pick three.";
 Rust:
 safe, fast, productive.
 Pick three.";
-        let result = search(query, content);
+        let result: Vec<SearchResult> = search(query, numbered(content), false).collect();
         assert_eq!(result.len(), 0);
     }
 
@@ -321,7 +751,8 @@ Rust:
 safe, fast, productive.
 needle in the haystack
 Pick three.";
-        let result = search_case_insensitive(query, content);
+        let result: Vec<SearchResult> =
+            search_case_insensitive(query, numbered(content), false).collect();
         assert_eq!(result.len(), 1, "Expected 1 result, got {}", result.len());
         assert_eq!(
             result[0].line, "needle in the haystack",
@@ -334,4 +765,39 @@ Pick three.";
             result[0].line_number
         );
     }
+
+    #[test]
+    fn parse_config_with_multiple_files() {
+        let test_args = vec![
+            "".to_string(),
+            "needle".to_string(),
+            "a.txt".to_string(),
+            "b.txt".to_string(),
+        ];
+        let config = Config::new_from_args(test_args.into_iter(), false).unwrap();
+        assert_eq!(config.query, "needle");
+        assert_eq!(
+            config.filenames,
+            vec!["a.txt".to_string(), "b.txt".to_string()]
+        );
+    }
+
+    #[test]
+    fn collect_paths_skips_directories_without_recursive() {
+        let paths = collect_paths(&[".".to_string()], false);
+        assert!(paths.is_empty());
+    }
+
+    #[test]
+    fn run_inverted_search() {
+        let query = "needle";
+        let content = "\
+Rust:
+safe, fast, productive.
+needle in the haystack
+Pick three.";
+        let result: Vec<SearchResult> = search(query, numbered(content), true).collect();
+        assert_eq!(result.len(), 3);
+        assert!(result.iter().all(|r| !r.line.contains(query)));
+    }
 }